@@ -1,16 +1,22 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use gloo::utils::window;
+use js_sys::Date;
 use serde::{Deserialize, Serialize};
-use web_sys::UrlSearchParams;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
-use wasm_bindgen::JsValue;
+use web_sys::UrlSearchParams;
+use yew::prelude::{UseStateHandle, use_effect_with, use_mut_ref};
 
 const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
-const CLIENT_ID: &str = "126932716262-m3jg96nhn9efg7mkee5k9d9aqnu0282l.apps.googleusercontent.com";
+pub const CLIENT_ID: &str = "126932716262-m3jg96nhn9efg7mkee5k9d9aqnu0282l.apps.googleusercontent.com";
 
 #[cfg(debug_assertions)]
-const REDIRECT_URI: &str = "http://localhost:8080/";
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
 #[cfg(not(debug_assertions))]
-const REDIRECT_URI: &str = "https://geeom.github.io/personal_assistant/";
+const REDIRECT_URI: &str = "https://geeom.github.io/personal_assistant/callback";
 
 #[cfg(debug_assertions)]
 pub const BACKEND_URL: &str = "http://localhost:3000";
@@ -20,15 +26,23 @@ pub const BACKEND_URL: &str = "https://personal-assistant-backend.fly.dev";
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthRequest {
     pub code: String,
+    pub code_verifier: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub token: String,
     pub user: UserInfo,
+    pub id_token_claims: IdTokenClaims,
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserInfo {
     pub id: i64,
     pub google_id: String,
@@ -36,16 +50,132 @@ pub struct UserInfo {
     pub name: String,
 }
 
-#[derive(Debug, Clone, Default)]
+/// The claims we care about from the Google ID token, as parsed server-side.
+/// The frontend uses `nonce` and `aud` to bind the returned identity back to
+/// the login attempt that started it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub nonce: String,
+    pub aud: String,
+    pub exp: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct AuthState {
     pub token: Option<String>,
     pub user: Option<UserInfo>,
+    pub refresh_token: Option<String>,
 }
 
 impl AuthState {
     pub fn is_authenticated(&self) -> bool {
         self.token.is_some()
     }
+
+    /// Applies a `/auth/refresh` response on top of the current session,
+    /// keeping the existing `refresh_token` if the response didn't include
+    /// a new one — the normal case for a backend that doesn't rotate
+    /// refresh tokens on every use. Using `AuthState::from` here instead
+    /// would wipe a still-valid refresh token and force a logout on the
+    /// very next refresh cycle.
+    pub fn refreshed(&self, response: AuthResponse) -> AuthState {
+        AuthState {
+            token: Some(response.token),
+            user: Some(response.user),
+            refresh_token: response.refresh_token.or_else(|| self.refresh_token.clone()),
+        }
+    }
+}
+
+/// Shared auth state, handed down through a `ContextProvider` so any route
+/// can read the signed-in user or update the session after login/logout.
+pub type AuthContext = UseStateHandle<AuthState>;
+
+/// Returns a handle that always reflects the latest `AuthState`, safe to
+/// read from inside a repeating timer or an unbounded async loop (a
+/// reconnect loop, a polling interval). Those closures are set up once and
+/// keep running across many renders, so a captured `AuthContext` snapshot
+/// inside them never observes a later `.set()` — even one made by the very
+/// closure itself on a previous tick. This hook mirrors every change into
+/// a `Rc<RefCell<_>>` whose identity is stable across renders, so a clone
+/// taken once when the closure is created keeps reading the current value.
+pub fn use_live_auth(auth_state: &AuthContext) -> Rc<RefCell<AuthState>> {
+    let live = use_mut_ref(AuthState::default);
+
+    {
+        let live = live.clone();
+        let snapshot = (**auth_state).clone();
+        use_effect_with(snapshot, move |state| {
+            *live.borrow_mut() = state.clone();
+        });
+    }
+
+    live
+}
+
+/// Returns true if the ID token's `nonce` matches the one saved before the
+/// login attempt started, and its `aud` matches our client id. These are
+/// the checks that bind the returned identity back to the login attempt
+/// that requested it and rule out a token minted for a different client.
+pub fn id_token_is_valid(claims: &IdTokenClaims, expected_nonce: Option<&str>, expected_aud: &str) -> bool {
+    expected_nonce == Some(claims.nonce.as_str()) && claims.aud == expected_aud
+}
+
+impl From<AuthResponse> for AuthState {
+    fn from(response: AuthResponse) -> Self {
+        AuthState {
+            token: Some(response.token),
+            user: Some(response.user),
+            refresh_token: response.refresh_token,
+        }
+    }
+}
+
+pub fn now_unix() -> i64 {
+    (Date::now() / 1000.0) as i64
+}
+
+/// Number of seconds before expiry at which we proactively refresh.
+pub const REFRESH_SKEW_SECONDS: i64 = 60;
+
+const SESSION_STORAGE_KEY: &str = "auth_session";
+
+pub fn save_session(state: &AuthState) {
+    if let Ok(Some(storage)) = window().local_storage() {
+        if let Ok(serialized) = serde_json::to_string(state) {
+            let _ = storage.set_item(SESSION_STORAGE_KEY, &serialized);
+        }
+    }
+}
+
+pub fn load_session() -> Option<AuthState> {
+    let serialized = window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(SESSION_STORAGE_KEY).ok())
+        .flatten()?;
+
+    serde_json::from_str(&serialized).ok()
+}
+
+pub fn clear_session() {
+    if let Ok(Some(storage)) = window().local_storage() {
+        let _ = storage.remove_item(SESSION_STORAGE_KEY);
+    }
+}
+
+/// Decodes the `exp` claim (seconds since epoch) out of a JWT's payload
+/// segment without verifying the signature; the token was already verified
+/// by the backend, this is only used to decide when to refresh.
+pub fn decode_jwt_exp(token: &str) -> Option<i64> {
+    let payload = token.split('.').nth(1)?;
+    let decoded = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&decoded).ok()?;
+    claims.get("exp")?.as_i64()
 }
 
 pub fn generate_state() -> String {
@@ -77,27 +207,109 @@ pub fn clear_saved_state() {
     }
 }
 
+pub fn generate_nonce() -> String {
+    Uuid::new_v4().to_string()
+}
+
+pub fn save_nonce(nonce: &str) {
+    if let Ok(storage) = window().local_storage() {
+        if let Some(storage) = storage {
+            let _ = storage.set_item("oauth_nonce", nonce);
+        }
+    }
+}
+
+pub fn get_saved_nonce() -> Option<String> {
+    window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item("oauth_nonce").ok())
+        .flatten()
+}
+
+pub fn clear_saved_nonce() {
+    if let Ok(storage) = window().local_storage() {
+        if let Some(storage) = storage {
+            let _ = storage.remove_item("oauth_nonce");
+        }
+    }
+}
+
+/// Generates a PKCE code verifier: the 32 bytes of two v4 UUIDs (244 random
+/// bits, the rest fixed version/variant bits), base64url-nopad encoded.
+/// The resulting 43 character string is drawn entirely from the unreserved
+/// set (`A-Z a-z 0-9 - _`) required by RFC 7636.
+pub fn generate_code_verifier() -> String {
+    let bytes = *Uuid::new_v4().as_bytes();
+    let more_bytes = *Uuid::new_v4().as_bytes();
+    let mut combined = [0u8; 32];
+    combined[..16].copy_from_slice(&bytes);
+    combined[16..].copy_from_slice(&more_bytes);
+    URL_SAFE_NO_PAD.encode(combined)
+}
+
+pub fn compute_code_challenge(verifier: &str) -> String {
+    let digest = Sha256::digest(verifier.as_bytes());
+    URL_SAFE_NO_PAD.encode(digest)
+}
+
+pub fn save_pkce_verifier(verifier: &str) {
+    if let Ok(storage) = window().local_storage() {
+        if let Some(storage) = storage {
+            let _ = storage.set_item("pkce_verifier", verifier);
+        }
+    }
+}
+
+pub fn get_saved_pkce_verifier() -> Option<String> {
+    window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item("pkce_verifier").ok())
+        .flatten()
+}
+
+pub fn clear_saved_pkce_verifier() {
+    if let Ok(storage) = window().local_storage() {
+        if let Some(storage) = storage {
+            let _ = storage.remove_item("pkce_verifier");
+        }
+    }
+}
+
 pub fn initiate_oauth_flow() {
     let state = generate_state();
     save_state(&state);
-    
+
+    let code_verifier = generate_code_verifier();
+    save_pkce_verifier(&code_verifier);
+    let code_challenge = compute_code_challenge(&code_verifier);
+
+    let nonce = generate_nonce();
+    save_nonce(&nonce);
+
     let params = [
         ("client_id", CLIENT_ID),
         ("redirect_uri", REDIRECT_URI),
         ("response_type", "code"),
         ("scope", "openid email profile"),
         ("state", &state),
-        ("access_type", "online"),
+        ("access_type", "offline"),
+        ("code_challenge", &code_challenge),
+        ("code_challenge_method", "S256"),
+        ("nonce", &nonce),
     ];
-    
+
     let query_string = params
         .iter()
         .map(|(k, v)| format!("{}={}", k, urlencoding::encode(v)))
         .collect::<Vec<_>>()
         .join("&");
-    
+
     let auth_url = format!("{}?{}", GOOGLE_AUTH_URL, query_string);
-    
+
     window().location().set_href(&auth_url).unwrap();
 }
 
@@ -117,28 +329,47 @@ pub fn parse_oauth_callback() -> Option<(String, String)> {
     Some((code, state))
 }
 
-pub fn clear_url_params() {
-    let location = window().location();
-    if let Ok(path) = location.pathname() {
-        let _ = window().history().unwrap()
-            .replace_state_with_url(&JsValue::NULL, "", Some(&path));
-    }
-}
+pub async fn exchange_code_for_token(
+    code: String,
+    code_verifier: String,
+) -> Result<AuthResponse, String> {
+    let request_body = AuthRequest { code, code_verifier };
 
-pub async fn exchange_code_for_token(code: String) -> Result<AuthResponse, String> {
-    let request_body = AuthRequest { code };
-    
     let response = gloo_net::http::Request::post(&format!("{}/auth/google", BACKEND_URL))
         .json(&request_body)
         .map_err(|e| format!("Failed to create request: {}", e))?
         .send()
         .await
         .map_err(|e| format!("Failed to send request: {}", e))?;
-    
+
+    clear_saved_pkce_verifier();
+
     if !response.ok() {
         return Err(format!("Authentication failed: {}", response.status()));
     }
-    
+
+    response
+        .json::<AuthResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// Exchanges a refresh token for a fresh access token, re-running the same
+/// checks the backend applies to the initial `/auth/google` exchange.
+pub async fn refresh_session(refresh_token: String) -> Result<AuthResponse, String> {
+    let request_body = RefreshRequest { refresh_token };
+
+    let response = gloo_net::http::Request::post(&format!("{}/auth/refresh", BACKEND_URL))
+        .json(&request_body)
+        .map_err(|e| format!("Failed to create request: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    if !response.ok() {
+        return Err(format!("Session refresh failed: {}", response.status()));
+    }
+
     response
         .json::<AuthResponse>()
         .await
@@ -157,4 +388,93 @@ mod urlencoding {
             })
             .collect()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_verifier_is_rfc7636_compliant() {
+        let verifier = generate_code_verifier();
+
+        assert!((43..=128).contains(&verifier.len()));
+        assert!(
+            verifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+    }
+
+    #[test]
+    fn code_verifier_is_not_reused_across_calls() {
+        assert_ne!(generate_code_verifier(), generate_code_verifier());
+    }
+
+    #[test]
+    fn code_challenge_is_deterministic_for_a_given_verifier() {
+        let verifier = generate_code_verifier();
+        assert_eq!(compute_code_challenge(&verifier), compute_code_challenge(&verifier));
+    }
+
+    #[test]
+    fn code_challenge_differs_for_different_verifiers() {
+        let a = generate_code_verifier();
+        let b = generate_code_verifier();
+        assert_ne!(compute_code_challenge(&a), compute_code_challenge(&b));
+    }
+
+    fn claims(nonce: &str, aud: &str) -> IdTokenClaims {
+        IdTokenClaims {
+            sub: "user-1".to_string(),
+            email: "user@example.com".to_string(),
+            email_verified: true,
+            nonce: nonce.to_string(),
+            aud: aud.to_string(),
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn id_token_is_valid_accepts_matching_nonce_and_aud() {
+        assert!(id_token_is_valid(&claims("abc", CLIENT_ID), Some("abc"), CLIENT_ID));
+    }
+
+    #[test]
+    fn id_token_is_valid_rejects_mismatched_nonce() {
+        assert!(!id_token_is_valid(&claims("abc", CLIENT_ID), Some("other"), CLIENT_ID));
+    }
+
+    #[test]
+    fn id_token_is_valid_rejects_missing_saved_nonce() {
+        assert!(!id_token_is_valid(&claims("abc", CLIENT_ID), None, CLIENT_ID));
+    }
+
+    #[test]
+    fn id_token_is_valid_rejects_mismatched_audience() {
+        assert!(!id_token_is_valid(&claims("abc", "someone-else"), Some("abc"), CLIENT_ID));
+    }
+
+    fn fake_jwt(payload_json: &str) -> String {
+        let header = URL_SAFE_NO_PAD.encode(b"{}");
+        let payload = URL_SAFE_NO_PAD.encode(payload_json.as_bytes());
+        format!("{header}.{payload}.signature")
+    }
+
+    #[test]
+    fn decode_jwt_exp_reads_the_exp_claim() {
+        let token = fake_jwt(r#"{"sub":"123","exp":1700000000}"#);
+        assert_eq!(decode_jwt_exp(&token), Some(1700000000));
+    }
+
+    #[test]
+    fn decode_jwt_exp_returns_none_without_a_payload_segment() {
+        assert_eq!(decode_jwt_exp("not-a-jwt"), None);
+    }
+
+    #[test]
+    fn decode_jwt_exp_returns_none_for_unparseable_payload() {
+        let token = format!("{}.{}.sig", URL_SAFE_NO_PAD.encode(b"{}"), "not-base64url!!");
+        assert_eq!(decode_jwt_exp(&token), None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,85 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::auth::{
+    AuthContext, AuthState, CLIENT_ID, clear_saved_nonce, clear_saved_state, exchange_code_for_token,
+    get_saved_nonce, get_saved_pkce_verifier, get_saved_state, id_token_is_valid, parse_oauth_callback,
+    save_session,
+};
+use crate::route::Route;
+use crate::toast::{ToastContext, ToastKind, push_toast};
+
+/// Landing page for the OAuth redirect (`/callback`). Verifies the `state`
+/// and PKCE verifier saved before the redirect, exchanges the code for a
+/// session, checks the ID token's `nonce`/`aud`, then navigates away —
+/// a dedicated route in place of stripping the query parameters off the
+/// current page, which the browser's back button behaves sensibly on.
+#[function_component(AuthCallbackPage)]
+pub fn auth_callback_page() -> Html {
+    let auth_state = use_context::<AuthContext>().expect("AuthContext not provided");
+    let toasts = use_context::<ToastContext>().expect("ToastContext not provided");
+    let navigator = use_navigator().expect("navigator not available");
+
+    use_effect_with((), move |()| {
+        let Some((code, state)) = parse_oauth_callback() else {
+            push_toast(&toasts, ToastKind::Error, "Missing OAuth callback parameters");
+            navigator.push(&Route::Home);
+            return;
+        };
+
+        let Some(saved_state) = get_saved_state() else {
+            push_toast(&toasts, ToastKind::Error, "Login request expired, please try again");
+            navigator.push(&Route::Home);
+            return;
+        };
+
+        if saved_state != state {
+            push_toast(&toasts, ToastKind::Error, "Login request expired, please try again");
+            navigator.push(&Route::Home);
+            return;
+        }
+        clear_saved_state();
+
+        let Some(code_verifier) = get_saved_pkce_verifier() else {
+            push_toast(&toasts, ToastKind::Error, "Missing PKCE verifier, please sign in again");
+            navigator.push(&Route::Home);
+            return;
+        };
+
+        wasm_bindgen_futures::spawn_local(async move {
+            match exchange_code_for_token(code, code_verifier).await {
+                Ok(auth_response) => {
+                    let saved_nonce = get_saved_nonce();
+                    let claims = &auth_response.id_token_claims;
+
+                    if !id_token_is_valid(claims, saved_nonce.as_deref(), CLIENT_ID) {
+                        clear_saved_nonce();
+                        push_toast(
+                            &toasts,
+                            ToastKind::Error,
+                            "Login verification failed, please sign in again",
+                        );
+                        navigator.push(&Route::Home);
+                        return;
+                    }
+
+                    clear_saved_nonce();
+                    let new_state = AuthState::from(auth_response);
+                    save_session(&new_state);
+                    auth_state.set(new_state);
+                    navigator.push(&Route::Conversation);
+                }
+                Err(e) => {
+                    push_toast(&toasts, ToastKind::Error, format!("Auth error: {e}"));
+                    navigator.push(&Route::Home);
+                }
+            }
+        });
+    });
+
+    html! {
+        <div style="text-align: center; padding: 40px;">
+            <p>{"⏳ Authenticating..."}</p>
+        </div>
+    }
+}
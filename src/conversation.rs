@@ -0,0 +1,341 @@
+use gloo_net::http::Request;
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::auth::{AuthContext, AuthState, BACKEND_URL, clear_session, use_live_auth};
+use crate::route::Route;
+use crate::toast::{ToastContext, ToastKind, push_toast};
+use crate::{CheckingContext, Message, MessageStatus, MessagesAction, MessagesHandle, MessagesState, ws};
+
+/// Posts a message to the backend, reconciling the optimistically-inserted
+/// local copy (matched by `client_id`) with the server's response, or
+/// marking it `Failed` so the UI can offer a retry.
+fn dispatch_send(token: String, message: Message, messages: MessagesHandle, toasts: ToastContext) {
+    let client_id = message.client_id.clone();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = Request::post(&format!("{BACKEND_URL}/messages"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&message)
+            .unwrap()
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.ok() => match response.json::<Message>().await {
+                Ok(mut new_message) => {
+                    new_message.status = MessageStatus::Sent;
+                    new_message.client_id = client_id.clone();
+                    messages.dispatch(MessagesAction::Reconcile { client_id, message: new_message });
+                    push_toast(&toasts, ToastKind::Success, "Message sent");
+                }
+                Err(e) => {
+                    messages.dispatch(MessagesAction::MarkFailed { client_id });
+                    push_toast(&toasts, ToastKind::Error, format!("Failed to parse response: {e}"));
+                }
+            },
+            Ok(response) => {
+                messages.dispatch(MessagesAction::MarkFailed { client_id });
+                push_toast(
+                    &toasts,
+                    ToastKind::Error,
+                    format!("Failed to send message: {}", response.status()),
+                );
+            }
+            Err(e) => {
+                messages.dispatch(MessagesAction::MarkFailed { client_id });
+                push_toast(&toasts, ToastKind::Error, format!("Failed to send message: {e}"));
+            }
+        }
+    });
+}
+
+#[function_component(ConversationPage)]
+pub fn conversation_page() -> Html {
+    let auth_state = use_context::<AuthContext>().expect("AuthContext not provided");
+    let checking = use_context::<CheckingContext>().expect("CheckingContext not provided");
+    let toasts = use_context::<ToastContext>().expect("ToastContext not provided");
+    let navigator = use_navigator().expect("navigator not available");
+    let messages = use_reducer(MessagesState::default);
+    let live_auth = use_live_auth(&auth_state);
+
+    // Redirect back to the landing page once we're sure there's no session.
+    {
+        let navigator = navigator.clone();
+        let is_checking = *checking;
+        let authenticated = auth_state.is_authenticated();
+
+        use_effect_with((is_checking, authenticated), move |(is_checking, authenticated)| {
+            if !*is_checking && !*authenticated {
+                navigator.push(&Route::Home);
+            }
+        });
+    }
+
+    // Stream messages live over a WebSocket while authenticated, falling
+    // back to a one-shot HTTP fetch if the socket never connects. Keyed on
+    // the token itself (not just whether we're authenticated) so a token
+    // refresh opens a fresh connection with the new token; the old
+    // connection is not closed here, but its reconnect loop stops the next
+    // time it disconnects. Without this, a refresh landing mid-reconnect
+    // would silently end live updates for the rest of the page's lifetime.
+    {
+        let messages = messages.clone();
+        let live_auth = live_auth.clone();
+        let toasts = toasts.clone();
+        let current_token = auth_state.token.clone();
+
+        use_effect_with(current_token, move |current_token| {
+            if let Some(token) = current_token.clone() {
+                let fallback_live_auth = live_auth.clone();
+                let fallback_messages = messages.clone();
+                let fallback_toasts = toasts.clone();
+
+                // `on_unavailable` fires once, when the first connection
+                // attempt ends, which can be well after this effect ran,
+                // so it reads the token through `live_auth` rather than
+                // the snapshot captured here — otherwise a token refreshed
+                // in the meantime would not be picked up.
+                let on_unavailable = move || {
+                    let Some(token) = fallback_live_auth.borrow().token.clone() else {
+                        return;
+                    };
+                    let messages = fallback_messages.clone();
+                    let toasts = fallback_toasts.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match Request::get(&format!("{BACKEND_URL}/messages"))
+                            .header("Authorization", &format!("Bearer {token}"))
+                            .send()
+                            .await
+                        {
+                            Ok(response) => {
+                                if let Ok(data) = response.json::<Vec<Message>>().await {
+                                    // Merges into the existing state rather than
+                                    // replacing it wholesale — a `ReplaceAll` here
+                                    // could wipe an optimistic send that's still
+                                    // in flight and hasn't reconciled yet.
+                                    messages.dispatch(MessagesAction::MergeFetched(data));
+                                }
+                            }
+                            Err(e) => {
+                                push_toast(
+                                    &toasts,
+                                    ToastKind::Error,
+                                    format!("Failed to fetch messages: {e}"),
+                                );
+                            }
+                        }
+                    });
+                };
+
+                // Reads through `live_auth` rather than the `auth_state`
+                // snapshot captured when this effect ran — otherwise a
+                // logout happening mid-connection would never be observed
+                // and the reconnect loop would run forever. A token
+                // refresh is handled separately: it changes `current_token`
+                // above, which re-runs this effect and opens a fresh
+                // connection with the new token, while this loop stops at
+                // its next disconnect because `active_token` no longer
+                // matches.
+                let active_token = token.clone();
+                let active_live_auth = live_auth.clone();
+                let is_active =
+                    move || active_live_auth.borrow().token.as_deref() == Some(active_token.as_str());
+
+                ws::connect(token, messages, on_unavailable, is_active);
+            }
+        });
+    }
+
+    let on_logout = {
+        let auth_state = auth_state.clone();
+        let messages = messages.clone();
+        let navigator = navigator.clone();
+
+        Callback::from(move |_| {
+            clear_session();
+            auth_state.set(AuthState::default());
+            messages.dispatch(MessagesAction::Clear);
+            navigator.push(&Route::Home);
+        })
+    };
+
+    let on_send_message = {
+        let auth_state = auth_state.clone();
+        let messages = messages.clone();
+        let toasts = toasts.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            if let Some(token) = &auth_state.token {
+                let token = token.clone();
+                let messages = messages.clone();
+                let toasts = toasts.clone();
+                let user = auth_state.user.clone();
+
+                if let Some(user) = user {
+                    let target = e.target_dyn_into::<web_sys::HtmlFormElement>().unwrap();
+                    let content = target
+                        .elements()
+                        .named_item("content")
+                        .unwrap()
+                        .dyn_into::<web_sys::HtmlInputElement>()
+                        .unwrap()
+                        .value();
+
+                    if !content.is_empty() {
+                        let message = Message {
+                            id: None,
+                            content,
+                            author: user.name.clone(),
+                            created_at: None,
+                            user_id: Some(user.id),
+                            status: MessageStatus::Pending,
+                            client_id: Some(Uuid::new_v4().to_string()),
+                        };
+
+                        messages.dispatch(MessagesAction::Insert(message.clone()));
+
+                        dispatch_send(token, message, messages, toasts);
+
+                        target.reset();
+                    }
+                }
+            }
+        })
+    };
+
+    if *checking {
+        return html! {
+            <div style="text-align: center; padding: 40px;">
+                <p>{"⏳ Checking authentication..."}</p>
+            </div>
+        };
+    }
+
+    html! {
+        <>
+            {if let Some(user) = &auth_state.user {
+                html! {
+                    <div style="display: flex; justify-content: space-between; align-items: center; margin-bottom: 20px; padding: 10px; background: #f5f5f5; border-radius: 8px;">
+                        <div>
+                            <strong>{"Signed in as: "}</strong>{&user.email}
+                        </div>
+                        <div style="display: flex; gap: 15px; align-items: center;">
+                            <Link<Route> to={Route::Settings}>{"Settings"}</Link<Route>>
+                            <button
+                                onclick={on_logout}
+                                style="background: #dc3545; color: white; border: none; padding: 5px 15px; border-radius: 4px; cursor: pointer;"
+                            >
+                                {"Sign Out"}
+                            </button>
+                        </div>
+                    </div>
+                }
+            } else {
+                html! {}
+            }}
+
+            <div style="margin-bottom: 20px;">
+                <h2>{"Messages"}</h2>
+
+                <form onsubmit={on_send_message} style="margin-bottom: 20px;">
+                    <div style="display: flex; gap: 10px;">
+                        <input
+                            type="text"
+                            name="content"
+                            placeholder="Type a message..."
+                            style="flex: 1; padding: 8px; border: 1px solid #ddd; border-radius: 4px;"
+                            required=true
+                        />
+                        <button
+                            type="submit"
+                            style="background: #28a745; color: white; border: none; padding: 8px 20px; border-radius: 4px; cursor: pointer;"
+                        >
+                            {"Send"}
+                        </button>
+                    </div>
+                </form>
+
+                <div style="border: 1px solid #ddd; border-radius: 8px; padding: 15px; min-height: 300px; max-height: 500px; overflow-y: auto;">
+                    {if messages.0.is_empty() {
+                        html! {
+                            <p style="text-align: center; color: #666;">{"No messages yet. Start a conversation!"}</p>
+                        }
+                    } else {
+                        html! {
+                            <div>
+                                {for messages.0.iter().map(|msg| {
+                                    let opacity = if msg.status == MessageStatus::Pending { "0.6" } else { "1" };
+
+                                    let retry_button = if msg.status == MessageStatus::Failed {
+                                        let token = auth_state.token.clone();
+                                        let messages = messages.clone();
+                                        let toasts = toasts.clone();
+                                        let retry_message = msg.clone();
+                                        let client_id = msg.client_id.clone();
+
+                                        let onclick = Callback::from(move |_| {
+                                            let Some(token) = token.clone() else {
+                                                return;
+                                            };
+
+                                            let mut retry_message = retry_message.clone();
+                                            retry_message.status = MessageStatus::Pending;
+                                            messages.dispatch(MessagesAction::Reconcile {
+                                                client_id: client_id.clone(),
+                                                message: retry_message.clone(),
+                                            });
+
+                                            dispatch_send(token, retry_message, messages.clone(), toasts.clone());
+                                        });
+
+                                        html! {
+                                            <button
+                                                onclick={onclick}
+                                                style="background: #dc3545; color: white; border: none; padding: 2px 10px; border-radius: 4px; cursor: pointer; font-size: 12px;"
+                                            >
+                                                {"Retry"}
+                                            </button>
+                                        }
+                                    } else {
+                                        html! {}
+                                    };
+
+                                    html! {
+                                        <div style={format!("margin-bottom: 15px; padding: 10px; background: #f9f9f9; border-radius: 4px; opacity: {opacity};")}>
+                                            <div style="display: flex; justify-content: space-between; margin-bottom: 5px;">
+                                                <strong>{&msg.author}</strong>
+                                                {if let Some(created_at) = &msg.created_at {
+                                                    html! { <small style="color: #666;">{created_at}</small> }
+                                                } else {
+                                                    html! {}
+                                                }}
+                                            </div>
+                                            <div>{&msg.content}</div>
+                                            {match msg.status {
+                                                MessageStatus::Pending => html! {
+                                                    <small style="color: #999;">{"Sending…"}</small>
+                                                },
+                                                MessageStatus::Failed => html! {
+                                                    <div style="display: flex; align-items: center; gap: 8px; margin-top: 5px;">
+                                                        <small style="color: #dc3545;">{"Failed to send"}</small>
+                                                        {retry_button}
+                                                    </div>
+                                                },
+                                                MessageStatus::Sent => html! {},
+                                            }}
+                                        </div>
+                                    }
+                                })}
+                            </div>
+                        }
+                    }}
+                </div>
+            </div>
+        </>
+    }
+}
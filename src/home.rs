@@ -0,0 +1,49 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::CheckingContext;
+use crate::auth::{AuthContext, initiate_oauth_flow};
+use crate::route::Route;
+
+#[function_component(HomePage)]
+pub fn home_page() -> Html {
+    let auth_state = use_context::<AuthContext>().expect("AuthContext not provided");
+    let checking = use_context::<CheckingContext>().expect("CheckingContext not provided");
+    let navigator = use_navigator().expect("navigator not available");
+
+    // Once we know whether a restored session is still valid, skip the
+    // landing page entirely for already-signed-in users.
+    {
+        let is_checking = *checking;
+        let authenticated = auth_state.is_authenticated();
+
+        use_effect_with((is_checking, authenticated), move |(is_checking, authenticated)| {
+            if !*is_checking && *authenticated {
+                navigator.push(&Route::Conversation);
+            }
+        });
+    }
+
+    if *checking {
+        return html! {
+            <div style="text-align: center; padding: 40px;">
+                <p>{"⏳ Checking authentication..."}</p>
+            </div>
+        };
+    }
+
+    let on_login = Callback::from(move |_| initiate_oauth_flow());
+
+    html! {
+        <div style="text-align: center; padding: 40px;">
+            <h2>{"Welcome!"}</h2>
+            <p>{"Please sign in with your Google account to continue."}</p>
+            <button
+                onclick={on_login}
+                style="background: #4285f4; color: white; border: none; padding: 10px 20px; border-radius: 4px; font-size: 16px; cursor: pointer; margin-top: 20px;"
+            >
+                {"Sign in with Google"}
+            </button>
+        </div>
+    }
+}
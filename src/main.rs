@@ -1,15 +1,42 @@
-use gloo_console as console;
-use gloo_net::http::Request;
+use std::rc::Rc;
+
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsCast;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
 mod auth;
 use auth::{
-    AuthState, BACKEND_URL, clear_saved_state, clear_url_params, exchange_code_for_token,
-    get_saved_state, initiate_oauth_flow, parse_oauth_callback,
+    AuthContext, AuthState, REFRESH_SKEW_SECONDS, clear_session, decode_jwt_exp, load_session,
+    now_unix, refresh_session, save_session, use_live_auth,
 };
 
+mod toast;
+use toast::{ToastContext, ToastKind, ToastViewer, ToastsState, dismiss_toast, push_toast};
+
+mod ws;
+
+mod route;
+use route::{Route, switch};
+
+mod callback;
+mod conversation;
+mod home;
+mod not_found;
+mod settings;
+
+/// Whether the initial "is there already a valid session?" check has
+/// finished. Routes use this to avoid redirecting before they actually
+/// know whether the user is signed in.
+type CheckingContext = UseStateHandle<bool>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum MessageStatus {
+    Pending,
+    #[default]
+    Sent,
+    Failed,
+}
+
 #[derive(Deserialize, Serialize, Clone, PartialEq)]
 struct Message {
     id: Option<i64>,
@@ -17,289 +44,241 @@ struct Message {
     author: String,
     created_at: Option<String>,
     user_id: Option<i64>,
+    /// Frontend-only: tracks optimistic send state, never sent to or read
+    /// from the backend.
+    #[serde(skip)]
+    status: MessageStatus,
+    /// Frontend-only: identifies an optimistically-inserted message so it
+    /// can be reconciled with (or retried against) the server.
+    #[serde(skip)]
+    client_id: Option<String>,
+}
+
+/// Backed by `use_reducer` rather than `use_state`: optimistic sends,
+/// retries and incoming WebSocket messages can all land while one
+/// another's async work is still in flight, and each previously read
+/// `(**messages).clone()` off a `UseStateHandle` snapshot captured before
+/// its own `await` — so two of those in flight concurrently would race on
+/// the same stale base and one would silently revert the other's change.
+/// Dispatching an action instead always applies against the reducer's
+/// actual current state.
+#[derive(Clone, PartialEq, Default)]
+struct MessagesState(Vec<Message>);
+
+enum MessagesAction {
+    Insert(Message),
+    Reconcile { client_id: Option<String>, message: Message },
+    MarkFailed { client_id: Option<String> },
+    MergeIncoming(Message),
+    MergeFetched(Vec<Message>),
+    Clear,
+}
+
+impl Reducible for MessagesState {
+    type Action = MessagesAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut current = self.0.clone();
+        match action {
+            MessagesAction::Insert(message) => current.insert(0, message),
+            MessagesAction::Reconcile { client_id, message } => {
+                if let Some(pos) = current.iter().position(|m| m.client_id == client_id) {
+                    current[pos] = message;
+                }
+            }
+            MessagesAction::MarkFailed { client_id } => {
+                if let Some(pos) = current.iter().position(|m| m.client_id == client_id) {
+                    current[pos].status = MessageStatus::Failed;
+                }
+            }
+            MessagesAction::MergeIncoming(incoming) => current = merge_incoming(current, incoming),
+            MessagesAction::MergeFetched(fetched) => current = merge_fetched(current, fetched),
+            MessagesAction::Clear => current.clear(),
+        }
+        Rc::new(MessagesState(current))
+    }
+}
+
+type MessagesHandle = UseReducerHandle<MessagesState>;
+
+/// Inserts `incoming` at the front, unless it's already represented in
+/// `current` — either by a matching (server-assigned) id, e.g. because it
+/// was already reconciled from our own optimistic send, or, for a send
+/// that hasn't been reconciled yet (still `id: None`), by matching
+/// author/content. The latter covers a backend that echoes a user's own
+/// sent message back over the socket: that echo can arrive before the
+/// HTTP response reconciles the optimistic entry, and it carries no
+/// `client_id` (a frontend-only field), so it can only be matched against
+/// the still-pending entry by content.
+fn merge_incoming(mut current: Vec<Message>, mut incoming: Message) -> Vec<Message> {
+    if incoming.id.is_some() && current.iter().any(|m| m.id == incoming.id) {
+        return current;
+    }
+
+    if let Some(pos) = current
+        .iter()
+        .position(|m| m.id.is_none() && m.author == incoming.author && m.content == incoming.content)
+    {
+        incoming.client_id = current[pos].client_id.clone();
+        incoming.status = MessageStatus::Sent;
+        current[pos] = incoming;
+        return current;
+    }
+
+    current.insert(0, incoming);
+    current
 }
 
-#[derive(Clone, PartialEq)]
-enum AppState {
-    CheckingAuth,
-    Unauthenticated,
-    Authenticated,
-    Loading,
-    Error(String),
+/// Merges a fallback HTTP fetch's message list into the current state,
+/// rather than replacing it outright: a `ReplaceAll` could otherwise wipe
+/// an optimistic send that's still in flight and hasn't been reconciled
+/// by the time the fetch lands.
+fn merge_fetched(current: Vec<Message>, fetched: Vec<Message>) -> Vec<Message> {
+    let pending: Vec<Message> = current.into_iter().filter(|m| m.id.is_none()).collect();
+    let mut merged = fetched;
+    for message in pending.into_iter().rev() {
+        merged.insert(0, message);
+    }
+    merged
 }
 
 #[function_component(App)]
 fn app() -> Html {
-    let auth_state = use_state(AuthState::default);
-    let app_state = use_state(|| AppState::CheckingAuth);
-    let messages = use_state(Vec::<Message>::new);
+    let auth_state: AuthContext = use_state(|| load_session().unwrap_or_default());
+    let checking: CheckingContext = use_state(|| true);
+    let toasts: ToastContext = use_reducer(ToastsState::default);
+    let live_auth = use_live_auth(&auth_state);
 
-    // Check for OAuth callback on mount
+    // Check whether a session restored from localStorage is still valid,
+    // refreshing it first if it's about to expire. The OAuth redirect
+    // itself is handled by `AuthCallbackPage`, not here.
     {
         let auth_state = auth_state.clone();
-        let app_state = app_state.clone();
+        let checking = checking.clone();
+        let toasts = toasts.clone();
 
         use_effect_with((), move |()| {
-            if let Some((code, state)) = parse_oauth_callback() {
-                // Verify state matches
-                if let Some(saved_state) = get_saved_state() {
-                    if saved_state == state {
-                        clear_saved_state();
-                        clear_url_params();
-
-                        wasm_bindgen_futures::spawn_local(async move {
-                            app_state.set(AppState::Loading);
-
-                            match exchange_code_for_token(code).await {
-                                Ok(auth_response) => {
-                                    auth_state.set(AuthState {
-                                        token: Some(auth_response.token),
-                                        user: Some(auth_response.user),
-                                    });
-                                    app_state.set(AppState::Authenticated);
-                                }
-                                Err(e) => {
-                                    console::error!(&format!("Auth error: {e}"));
-                                    app_state.set(AppState::Error(e));
-                                }
-                            }
-                        });
-                    } else {
-                        console::error!("State mismatch in OAuth callback");
-                        app_state.set(AppState::Unauthenticated);
+            let Some(token) = auth_state.token.clone() else {
+                checking.set(false);
+                return;
+            };
+
+            let exp = decode_jwt_exp(&token);
+            let needs_refresh = exp.map_or(true, |exp| exp - now_unix() <= REFRESH_SKEW_SECONDS);
+
+            if !needs_refresh {
+                checking.set(false);
+                return;
+            }
+
+            let Some(refresh_token) = auth_state.refresh_token.clone() else {
+                clear_session();
+                auth_state.set(AuthState::default());
+                checking.set(false);
+                return;
+            };
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match refresh_session(refresh_token).await {
+                    Ok(auth_response) => {
+                        let new_state = auth_state.refreshed(auth_response);
+                        save_session(&new_state);
+                        auth_state.set(new_state);
+                    }
+                    Err(e) => {
+                        push_toast(&toasts, ToastKind::Error, format!("Session expired: {e}"));
+                        clear_session();
+                        auth_state.set(AuthState::default());
                     }
-                } else {
-                    console::error!("No saved state found");
-                    app_state.set(AppState::Unauthenticated);
-                }
-            } else {
-                // No callback params, check if we have existing auth
-                if auth_state.is_authenticated() {
-                    app_state.set(AppState::Authenticated);
-                } else {
-                    app_state.set(AppState::Unauthenticated);
                 }
-            }
+                checking.set(false);
+            });
         });
     }
 
-    // Load messages when authenticated
+    // While authenticated, periodically check the token's expiry and
+    // transparently refresh it shortly before it lapses. The interval is
+    // only recreated when `authenticated` flips, so its closure reads the
+    // token through `live_auth` rather than a captured `AuthContext`
+    // snapshot — otherwise every tick after the first refresh would keep
+    // reading the pre-refresh token and resubmit it forever.
     {
-        let messages = messages.clone();
         let auth_state = auth_state.clone();
-        let app_state_val = (*app_state).clone();
+        let live_auth = live_auth.clone();
+        let toasts = toasts.clone();
+        let authenticated = auth_state.is_authenticated();
 
-        use_effect_with(app_state_val, move |state| {
-            if matches!(state, AppState::Authenticated) {
-                if let Some(token) = &auth_state.token {
-                    let token = token.clone();
+        use_effect_with(authenticated, move |authenticated| {
+            let interval = if *authenticated {
+                let auth_state = auth_state.clone();
+                let live_auth = live_auth.clone();
+                let toasts = toasts.clone();
+                Some(gloo::timers::callback::Interval::new(15_000, move || {
+                    let (token, refresh_token) = {
+                        let live = live_auth.borrow();
+                        (live.token.clone(), live.refresh_token.clone())
+                    };
+
+                    let Some(token) = token else {
+                        return;
+                    };
+                    let Some(exp) = decode_jwt_exp(&token) else {
+                        return;
+                    };
+                    if exp - now_unix() > REFRESH_SKEW_SECONDS {
+                        return;
+                    }
+                    let Some(refresh_token) = refresh_token else {
+                        return;
+                    };
+
+                    let auth_state = auth_state.clone();
+                    let live_auth = live_auth.clone();
+                    let toasts = toasts.clone();
                     wasm_bindgen_futures::spawn_local(async move {
-                        match Request::get(&format!("{BACKEND_URL}/messages"))
-                            .header("Authorization", &format!("Bearer {token}"))
-                            .send()
-                            .await
-                        {
-                            Ok(response) => {
-                                if let Ok(data) = response.json::<Vec<Message>>().await {
-                                    messages.set(data);
-                                }
+                        match refresh_session(refresh_token).await {
+                            Ok(auth_response) => {
+                                let new_state = live_auth.borrow().refreshed(auth_response);
+                                save_session(&new_state);
+                                auth_state.set(new_state);
                             }
                             Err(e) => {
-                                console::error!(&format!("Failed to fetch messages: {e}"));
+                                push_toast(
+                                    &toasts,
+                                    ToastKind::Error,
+                                    format!("Session refresh failed: {e}"),
+                                );
                             }
                         }
                     });
-                }
-            }
+                }))
+            } else {
+                None
+            };
+
+            move || drop(interval)
         });
     }
 
-    let on_login = {
-        Callback::from(move |_| {
-            initiate_oauth_flow();
-        })
-    };
-
-    let on_logout = {
-        let auth_state = auth_state.clone();
-        let app_state = app_state.clone();
-        let messages = messages.clone();
-
-        Callback::from(move |_| {
-            auth_state.set(AuthState::default());
-            app_state.set(AppState::Unauthenticated);
-            messages.set(vec![]);
-        })
-    };
-
-    let on_send_message = {
-        let auth_state = auth_state.clone();
-        let messages = messages.clone();
-
-        Callback::from(move |e: SubmitEvent| {
-            e.prevent_default();
-
-            if let Some(token) = &auth_state.token {
-                let token = token.clone();
-                let messages = messages.clone();
-                let user = auth_state.user.clone();
-
-                if let Some(user) = user {
-                    let target = e.target_dyn_into::<web_sys::HtmlFormElement>().unwrap();
-                    let content = target
-                        .elements()
-                        .named_item("content")
-                        .unwrap()
-                        .dyn_into::<web_sys::HtmlInputElement>()
-                        .unwrap()
-                        .value();
-
-                    if !content.is_empty() {
-                        let message = Message {
-                            id: None,
-                            content,
-                            author: user.name.clone(),
-                            created_at: None,
-                            user_id: Some(user.id),
-                        };
-
-                        wasm_bindgen_futures::spawn_local(async move {
-                            match Request::post(&format!("{BACKEND_URL}/messages"))
-                                .header("Authorization", &format!("Bearer {token}"))
-                                .json(&message)
-                                .unwrap()
-                                .send()
-                                .await
-                            {
-                                Ok(response) => {
-                                    if let Ok(new_message) = response.json::<Message>().await {
-                                        let mut current_messages = (*messages).clone();
-                                        current_messages.insert(0, new_message);
-                                        messages.set(current_messages);
-                                    }
-                                }
-                                Err(e) => {
-                                    console::error!(&format!("Failed to send message: {e}"));
-                                }
-                            }
-                        });
-
-                        target.reset();
-                    }
-                }
-            }
-        })
+    let on_dismiss_toast = {
+        let toasts = toasts.clone();
+        Callback::from(move |id: String| dismiss_toast(&toasts, &id))
     };
 
     html! {
-        <div style="max-width: 800px; margin: 0 auto; padding: 20px;">
-            <h1>{"Personal Assistant"}</h1>
-
-            {match &*app_state {
-                AppState::CheckingAuth => html! {
-                    <div style="text-align: center; padding: 40px;">
-                        <p>{"⏳ Checking authentication..."}</p>
-                    </div>
-                },
-                AppState::Loading => html! {
-                    <div style="text-align: center; padding: 40px;">
-                        <p>{"⏳ Authenticating..."}</p>
-                    </div>
-                },
-                AppState::Unauthenticated => html! {
-                    <div style="text-align: center; padding: 40px;">
-                        <h2>{"Welcome!"}</h2>
-                        <p>{"Please sign in with your Google account to continue."}</p>
-                        <button
-                            onclick={on_login}
-                            style="background: #4285f4; color: white; border: none; padding: 10px 20px; border-radius: 4px; font-size: 16px; cursor: pointer; margin-top: 20px;"
-                        >
-                            {"Sign in with Google"}
-                        </button>
-                    </div>
-                },
-                AppState::Error(error) => html! {
-                    <div style="text-align: center; padding: 40px;">
-                        <p style="color: red;">{format!("❌ Error: {}", error)}</p>
-                        <button
-                            onclick={on_login}
-                            style="background: #4285f4; color: white; border: none; padding: 10px 20px; border-radius: 4px; font-size: 16px; cursor: pointer; margin-top: 20px;"
-                        >
-                            {"Try Again"}
-                        </button>
-                    </div>
-                },
-                AppState::Authenticated => html! {
-                    <>
-                        {if let Some(user) = &auth_state.user {
-                            html! {
-                                <div style="display: flex; justify-content: space-between; align-items: center; margin-bottom: 20px; padding: 10px; background: #f5f5f5; border-radius: 8px;">
-                                    <div>
-                                        <strong>{"Signed in as: "}</strong>{&user.email}
-                                    </div>
-                                    <button
-                                        onclick={on_logout}
-                                        style="background: #dc3545; color: white; border: none; padding: 5px 15px; border-radius: 4px; cursor: pointer;"
-                                    >
-                                        {"Sign Out"}
-                                    </button>
-                                </div>
-                            }
-                        } else {
-                            html! {}
-                        }}
-
-                        <div style="margin-bottom: 20px;">
-                            <h2>{"Messages"}</h2>
-
-                            <form onsubmit={on_send_message} style="margin-bottom: 20px;">
-                                <div style="display: flex; gap: 10px;">
-                                    <input
-                                        type="text"
-                                        name="content"
-                                        placeholder="Type a message..."
-                                        style="flex: 1; padding: 8px; border: 1px solid #ddd; border-radius: 4px;"
-                                        required=true
-                                    />
-                                    <button
-                                        type="submit"
-                                        style="background: #28a745; color: white; border: none; padding: 8px 20px; border-radius: 4px; cursor: pointer;"
-                                    >
-                                        {"Send"}
-                                    </button>
-                                </div>
-                            </form>
-
-                            <div style="border: 1px solid #ddd; border-radius: 8px; padding: 15px; min-height: 300px; max-height: 500px; overflow-y: auto;">
-                                {if messages.is_empty() {
-                                    html! {
-                                        <p style="text-align: center; color: #666;">{"No messages yet. Start a conversation!"}</p>
-                                    }
-                                } else {
-                                    html! {
-                                        <div>
-                                            {for messages.iter().map(|msg| html! {
-                                                <div style="margin-bottom: 15px; padding: 10px; background: #f9f9f9; border-radius: 4px;">
-                                                    <div style="display: flex; justify-content: space-between; margin-bottom: 5px;">
-                                                        <strong>{&msg.author}</strong>
-                                                        {if let Some(created_at) = &msg.created_at {
-                                                            html! { <small style="color: #666;">{created_at}</small> }
-                                                        } else {
-                                                            html! {}
-                                                        }}
-                                                    </div>
-                                                    <div>{&msg.content}</div>
-                                                </div>
-                                            })}
-                                        </div>
-                                    }
-                                }}
-                            </div>
+        <ContextProvider<AuthContext> context={auth_state}>
+            <ContextProvider<CheckingContext> context={checking}>
+                <ContextProvider<ToastContext> context={toasts.clone()}>
+                    <BrowserRouter>
+                        <div style="max-width: 800px; margin: 0 auto; padding: 20px;">
+                            <ToastViewer toasts={toasts.0.clone()} on_dismiss={on_dismiss_toast} />
+                            <h1>{"Personal Assistant"}</h1>
+                            <Switch<Route> render={switch} />
                         </div>
-                    </>
-                }
-            }}
-        </div>
+                    </BrowserRouter>
+                </ContextProvider<ToastContext>>
+            </ContextProvider<CheckingContext>>
+        </ContextProvider<AuthContext>>
     }
 }
 
@@ -308,3 +287,76 @@ fn main() {
         .render();
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: Option<i64>, content: &str) -> Message {
+        Message {
+            id,
+            content: content.to_string(),
+            author: "someone".to_string(),
+            created_at: None,
+            user_id: None,
+            status: MessageStatus::Sent,
+            client_id: None,
+        }
+    }
+
+    #[test]
+    fn merge_incoming_prepends_a_message_with_no_id() {
+        let current = vec![message(Some(1), "first")];
+        let result = merge_incoming(current, message(None, "second"));
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content, "second");
+    }
+
+    #[test]
+    fn merge_incoming_prepends_a_message_with_a_new_id() {
+        let current = vec![message(Some(1), "first")];
+        let result = merge_incoming(current, message(Some(2), "second"));
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content, "second");
+    }
+
+    #[test]
+    fn merge_incoming_drops_a_message_whose_id_is_already_present() {
+        let current = vec![message(Some(1), "first")];
+        let result = merge_incoming(current, message(Some(1), "duplicate"));
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content, "first");
+    }
+
+    #[test]
+    fn merge_incoming_reconciles_an_echo_of_a_still_pending_send() {
+        let mut pending = message(None, "hello");
+        pending.client_id = Some("local-1".to_string());
+        pending.status = MessageStatus::Pending;
+
+        let mut echo = message(Some(7), "hello");
+        echo.client_id = None;
+
+        let result = merge_incoming(vec![pending], echo);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id, Some(7));
+        assert_eq!(result[0].status, MessageStatus::Sent);
+        assert_eq!(result[0].client_id, Some("local-1".to_string()));
+    }
+
+    #[test]
+    fn merge_fetched_keeps_not_yet_reconciled_local_sends() {
+        let mut pending = message(None, "still sending");
+        pending.client_id = Some("local-1".to_string());
+        pending.status = MessageStatus::Pending;
+
+        let result = merge_fetched(vec![pending.clone()], vec![message(Some(1), "first")]);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].client_id, pending.client_id);
+        assert_eq!(result[1].id, Some(1));
+    }
+}
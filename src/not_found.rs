@@ -0,0 +1,15 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::route::Route;
+
+#[function_component(NotFoundPage)]
+pub fn not_found_page() -> Html {
+    html! {
+        <div style="text-align: center; padding: 40px;">
+            <h2>{"404"}</h2>
+            <p>{"That page doesn't exist."}</p>
+            <Link<Route> to={Route::Home}>{"Back home"}</Link<Route>>
+        </div>
+    }
+}
@@ -0,0 +1,33 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::callback::AuthCallbackPage;
+use crate::conversation::ConversationPage;
+use crate::home::HomePage;
+use crate::not_found::NotFoundPage;
+use crate::settings::SettingsPage;
+
+#[derive(Clone, Routable, PartialEq)]
+pub enum Route {
+    #[at("/")]
+    Home,
+    #[at("/conversation")]
+    Conversation,
+    #[at("/settings")]
+    Settings,
+    #[at("/callback")]
+    AuthCallback,
+    #[not_found]
+    #[at("/404")]
+    NotFound,
+}
+
+pub fn switch(route: Route) -> Html {
+    match route {
+        Route::Home => html! { <HomePage /> },
+        Route::Conversation => html! { <ConversationPage /> },
+        Route::Settings => html! { <SettingsPage /> },
+        Route::AuthCallback => html! { <AuthCallbackPage /> },
+        Route::NotFound => html! { <NotFoundPage /> },
+    }
+}
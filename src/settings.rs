@@ -0,0 +1,55 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::CheckingContext;
+use crate::auth::{AuthContext, AuthState, clear_session};
+use crate::route::Route;
+
+#[function_component(SettingsPage)]
+pub fn settings_page() -> Html {
+    let auth_state = use_context::<AuthContext>().expect("AuthContext not provided");
+    let checking = use_context::<CheckingContext>().expect("CheckingContext not provided");
+    let navigator = use_navigator().expect("navigator not available");
+
+    {
+        let navigator = navigator.clone();
+        let is_checking = *checking;
+        let authenticated = auth_state.is_authenticated();
+
+        use_effect_with((is_checking, authenticated), move |(is_checking, authenticated)| {
+            if !*is_checking && !*authenticated {
+                navigator.push(&Route::Home);
+            }
+        });
+    }
+
+    let on_logout = {
+        let auth_state = auth_state.clone();
+        let navigator = navigator.clone();
+        Callback::from(move |_| {
+            clear_session();
+            auth_state.set(AuthState::default());
+            navigator.push(&Route::Home);
+        })
+    };
+
+    html! {
+        <div style="padding: 20px;">
+            <h2>{"Settings"}</h2>
+            {if let Some(user) = &auth_state.user {
+                html! { <p><strong>{"Signed in as: "}</strong>{&user.email}</p> }
+            } else {
+                html! {}
+            }}
+            <button
+                onclick={on_logout}
+                style="background: #dc3545; color: white; border: none; padding: 5px 15px; border-radius: 4px; cursor: pointer;"
+            >
+                {"Sign Out"}
+            </button>
+            <p style="margin-top: 20px;">
+                <Link<Route> to={Route::Conversation}>{"Back to conversation"}</Link<Route>>
+            </p>
+        </div>
+    }
+}
@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use uuid::Uuid;
+use yew::prelude::*;
+
+pub const TOAST_DURATION_MS: u32 = 4_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub id: String,
+    pub kind: ToastKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ToastsState(pub Vec<Toast>);
+
+pub enum ToastsAction {
+    Push(Toast),
+    Dismiss(String),
+}
+
+impl Reducible for ToastsState {
+    type Action = ToastsAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut current = self.0.clone();
+        match action {
+            ToastsAction::Push(toast) => current.push(toast),
+            ToastsAction::Dismiss(id) => current.retain(|t| t.id != id),
+        }
+        Rc::new(ToastsState(current))
+    }
+}
+
+/// Backed by `use_reducer` rather than `use_state`: each auto-dismiss
+/// timer dispatches against whatever the queue's state actually is when
+/// it fires, instead of a `Vec<Toast>` snapshot captured at push time —
+/// so an overlapping push or dismissal in the intervening 4 seconds can't
+/// get silently reverted.
+pub type ToastQueue = UseReducerHandle<ToastsState>;
+
+/// The toast queue, shared through a `ContextProvider` so any route can
+/// surface a notification without threading it through props.
+pub type ToastContext = ToastQueue;
+
+/// Appends a toast to the queue and schedules its own auto-dismissal.
+pub fn push_toast(toasts: &ToastQueue, kind: ToastKind, message: impl Into<String>) {
+    let toast = Toast {
+        id: Uuid::new_v4().to_string(),
+        kind,
+        message: message.into(),
+    };
+    let id = toast.id.clone();
+    toasts.dispatch(ToastsAction::Push(toast));
+
+    let toasts = toasts.clone();
+    gloo::timers::callback::Timeout::new(TOAST_DURATION_MS, move || {
+        toasts.dispatch(ToastsAction::Dismiss(id));
+    })
+    .forget();
+}
+
+pub fn dismiss_toast(toasts: &ToastQueue, id: &str) {
+    toasts.dispatch(ToastsAction::Dismiss(id.to_string()));
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ToastViewerProps {
+    pub toasts: Vec<Toast>,
+    pub on_dismiss: Callback<String>,
+}
+
+#[function_component(ToastViewer)]
+pub fn toast_viewer(props: &ToastViewerProps) -> Html {
+    html! {
+        <div style="position: fixed; top: 20px; right: 20px; z-index: 1000; display: flex; flex-direction: column; gap: 10px; max-width: 320px;">
+            {for props.toasts.iter().map(|toast| {
+                let (background, icon) = match toast.kind {
+                    ToastKind::Info => ("#2196f3", "ℹ️"),
+                    ToastKind::Success => ("#28a745", "✅"),
+                    ToastKind::Error => ("#dc3545", "❌"),
+                };
+                let id = toast.id.clone();
+                let on_dismiss = props.on_dismiss.clone();
+
+                html! {
+                    <div
+                        key={toast.id.clone()}
+                        onclick={Callback::from(move |_| on_dismiss.emit(id.clone()))}
+                        style={format!("background: {background}; color: white; padding: 10px 15px; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 6px rgba(0, 0, 0, 0.2);")}
+                    >
+                        {format!("{icon} {}", toast.message)}
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
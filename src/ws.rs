@@ -0,0 +1,70 @@
+use crate::auth::BACKEND_URL;
+use crate::{Message, MessagesAction, MessagesHandle};
+use futures::StreamExt;
+use gloo::timers::future::sleep;
+use gloo_net::websocket::{Message as WsMessage, futures::WebSocket};
+use std::time::Duration;
+
+const INITIAL_BACKOFF_MS: u64 = 1_000;
+const MAX_BACKOFF_MS: u64 = 30_000;
+
+fn ws_url(token: &str) -> String {
+    let base = BACKEND_URL.replacen("http", "ws", 1);
+    format!("{base}/ws?token={token}")
+}
+
+/// Opens a live WebSocket connection to the message stream and merges
+/// incoming messages into `messages`, reconnecting with exponential
+/// backoff on disconnect. Calls `on_unavailable` once if the first
+/// connection attempt ends without a single frame having been received,
+/// so the caller can fall back to a one-shot HTTP fetch. Stops reconnecting once `is_active` returns false (e.g. the
+/// user logged out or a different token is now in effect).
+pub fn connect(
+    token: String,
+    messages: MessagesHandle,
+    on_unavailable: impl Fn() + 'static,
+    is_active: impl Fn() -> bool + 'static,
+) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let mut backoff_ms = INITIAL_BACKOFF_MS;
+        let mut ever_connected = false;
+        let mut notified_unavailable = false;
+
+        while is_active() {
+            match WebSocket::open(&ws_url(&token)) {
+                Ok(ws) => {
+                    // `WebSocket::open` returns before the handshake, so only
+                    // a received frame shows the connection actually came up.
+                    let (_write, mut read) = ws.split();
+                    while let Some(frame) = read.next().await {
+                        match frame {
+                            Ok(frame) => {
+                                ever_connected = true;
+                                backoff_ms = INITIAL_BACKOFF_MS;
+                                if let WsMessage::Text(text) = frame {
+                                    if let Ok(incoming) = serde_json::from_str::<Message>(&text) {
+                                        messages.dispatch(MessagesAction::MergeIncoming(incoming));
+                                    }
+                                }
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                }
+                Err(_) => {}
+            }
+
+            if !ever_connected && !notified_unavailable {
+                on_unavailable();
+                notified_unavailable = true;
+            }
+
+            if !is_active() {
+                break;
+            }
+
+            sleep(Duration::from_millis(backoff_ms)).await;
+            backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
+        }
+    });
+}